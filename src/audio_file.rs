@@ -0,0 +1,273 @@
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// A whole audio file decoded to mono `f64` samples at the file's native
+/// sample rate, ready to be resampled and fed through [`crate::lip_sync_job::LipSyncJob`]
+/// for offline baking.
+pub struct DecodedAudio {
+    pub samples: Vec<f64>,
+    pub sample_rate: i64,
+}
+
+/// Decodes `path` based on its extension. Supports WAV (PCM, via a small
+/// in-house RIFF reader), OGG Vorbis (via `lewton`), and FLAC (via `claxon`).
+pub fn decode_file(path: &str) -> Result<DecodedAudio, String> {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    match ext.as_str() {
+        "wav" => decode_wav(path),
+        "ogg" => decode_ogg(path),
+        "flac" => decode_flac(path),
+        _ => Err(format!("unsupported audio file extension: {}", ext)),
+    }
+}
+
+// Linear-interpolation resample, good enough for analysis purposes (the
+// profile's `target_sample_rate` is typically 16 kHz).
+pub fn resample(samples: &[f64], from_rate: i64, to_rate: i64) -> Vec<f64> {
+    if samples.is_empty() || from_rate == to_rate {
+        return samples.to_vec();
+    }
+
+    let ratio = from_rate as f64 / to_rate as f64;
+    let out_len = (samples.len() as f64 / ratio).ceil() as usize;
+    let mut output = Vec::with_capacity(out_len);
+
+    for i in 0..out_len {
+        let src_pos = i as f64 * ratio;
+        let src_index = src_pos as usize;
+        let frac = src_pos - src_index as f64;
+        let a = samples[src_index.min(samples.len() - 1)];
+        let b = samples[(src_index + 1).min(samples.len() - 1)];
+        output.push(a + (b - a) * frac);
+    }
+
+    output
+}
+
+fn decode_wav(path: &str) -> Result<DecodedAudio, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).map_err(|e| e.to_string())?;
+
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return Err("not a RIFF/WAVE file".to_string());
+    }
+
+    let mut channels: u16 = 1;
+    let mut sample_rate: u32 = 0;
+    let mut bits_per_sample: u16 = 16;
+    let mut samples = Vec::new();
+
+    let mut offset = 12;
+    while offset + 8 <= data.len() {
+        let chunk_id = &data[offset..offset + 4];
+        let chunk_size =
+            u32::from_le_bytes(data[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        let body_start = offset + 8;
+        let body_end = (body_start + chunk_size).min(data.len());
+
+        if chunk_id == b"fmt " && body_end >= body_start + 16 {
+            channels = u16::from_le_bytes(data[body_start + 2..body_start + 4].try_into().unwrap());
+            sample_rate =
+                u32::from_le_bytes(data[body_start + 4..body_start + 8].try_into().unwrap());
+            bits_per_sample =
+                u16::from_le_bytes(data[body_start + 14..body_start + 16].try_into().unwrap());
+        } else if chunk_id == b"data" {
+            samples = decode_pcm(
+                &data[body_start..body_end],
+                channels as usize,
+                bits_per_sample,
+            );
+        }
+
+        // Chunks are word-aligned; odd-sized chunks have a pad byte.
+        offset = body_start + chunk_size + (chunk_size % 2);
+    }
+
+    if sample_rate == 0 {
+        return Err("missing fmt chunk".to_string());
+    }
+
+    Ok(DecodedAudio {
+        samples,
+        sample_rate: sample_rate as i64,
+    })
+}
+
+fn decode_pcm(body: &[u8], channels: usize, bits_per_sample: u16) -> Vec<f64> {
+    let bytes_per_sample = (bits_per_sample / 8).max(1) as usize;
+    let frame_size = bytes_per_sample * channels.max(1);
+    if frame_size == 0 {
+        return vec![];
+    }
+
+    let mut mono = Vec::with_capacity(body.len() / frame_size);
+    for frame in body.chunks_exact(frame_size) {
+        let mut sum = 0.0;
+        for c in 0..channels {
+            let start = c * bytes_per_sample;
+            let sample = match bits_per_sample {
+                16 => {
+                    i16::from_le_bytes(frame[start..start + 2].try_into().unwrap()) as f64
+                        / i16::MAX as f64
+                }
+                8 => (frame[start] as f64 - 128.0) / 128.0,
+                32 => {
+                    i32::from_le_bytes(frame[start..start + 4].try_into().unwrap()) as f64
+                        / i32::MAX as f64
+                }
+                _ => 0.0,
+            };
+            sum += sample;
+        }
+        mono.push(sum / channels as f64);
+    }
+
+    mono
+}
+
+fn decode_ogg(path: &str) -> Result<DecodedAudio, String> {
+    use lewton::inside_ogg::OggStreamReader;
+
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut reader = OggStreamReader::new(file).map_err(|e| e.to_string())?;
+    let channels = reader.ident_hdr.audio_channels as usize;
+    let sample_rate = reader.ident_hdr.audio_sample_rate as i64;
+
+    let mut mono = Vec::new();
+    while let Some(packet) = reader.read_dec_packet_itl().map_err(|e| e.to_string())? {
+        for frame in packet.chunks(channels.max(1)) {
+            let sum: i32 = frame.iter().map(|&s| s as i32).sum();
+            mono.push(sum as f64 / channels.max(1) as f64 / i16::MAX as f64);
+        }
+    }
+
+    Ok(DecodedAudio {
+        samples: mono,
+        sample_rate,
+    })
+}
+
+fn decode_flac(path: &str) -> Result<DecodedAudio, String> {
+    use claxon::FlacReader;
+
+    let mut reader = FlacReader::open(path).map_err(|e| e.to_string())?;
+    let info = reader.streaminfo();
+    let channels = info.channels as usize;
+    let sample_rate = info.sample_rate as i64;
+    let max_value = (1i64 << (info.bits_per_sample - 1)) as f64;
+
+    let mut mono = Vec::new();
+    let mut frame = Vec::with_capacity(channels);
+    for sample in reader.samples() {
+        frame.push(sample.map_err(|e| e.to_string())?);
+        if frame.len() == channels.max(1) {
+            let sum: i64 = frame.iter().map(|&s| s as i64).sum();
+            mono.push(sum as f64 / channels.max(1) as f64 / max_value);
+            frame.clear();
+        }
+    }
+
+    Ok(DecodedAudio {
+        samples: mono,
+        sample_rate,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn resample_identity_when_rates_match() {
+        let samples = vec![0.1, 0.2, 0.3];
+        assert_eq!(resample(&samples, 16_000, 16_000), samples);
+    }
+
+    #[test]
+    fn resample_halves_length_when_rate_doubles() {
+        let samples = vec![0.0, 1.0, 2.0, 3.0];
+        let out = resample(&samples, 2, 1);
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0], 0.0);
+    }
+
+    #[test]
+    fn resample_empty_is_empty() {
+        assert!(resample(&[], 44_100, 16_000).is_empty());
+    }
+
+    // Builds a minimal mono 16-bit PCM WAV: "RIFF" + size, "WAVE", "fmt "
+    // chunk, "data" chunk.
+    fn make_wav(sample_rate: u32, samples: &[i16]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for s in samples {
+            data.extend_from_slice(&s.to_le_bytes());
+        }
+
+        let byte_rate = sample_rate * 2;
+        let mut fmt = Vec::new();
+        fmt.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        fmt.extend_from_slice(&1u16.to_le_bytes()); // mono
+        fmt.extend_from_slice(&sample_rate.to_le_bytes());
+        fmt.extend_from_slice(&byte_rate.to_le_bytes());
+        fmt.extend_from_slice(&2u16.to_le_bytes()); // block align
+        fmt.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+
+        let mut body = Vec::new();
+        body.extend_from_slice(b"WAVE");
+        body.extend_from_slice(b"fmt ");
+        body.extend_from_slice(&(fmt.len() as u32).to_le_bytes());
+        body.extend_from_slice(&fmt);
+        body.extend_from_slice(b"data");
+        body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        body.extend_from_slice(&data);
+
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&body);
+        wav
+    }
+
+    #[test]
+    fn decode_wav_reads_sample_rate_and_pcm_samples() {
+        let wav = make_wav(16_000, &[0, i16::MAX, i16::MIN]);
+        let path = std::env::temp_dir().join("rlip_sync_test_decode_wav.wav");
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(&wav)
+            .unwrap();
+
+        let decoded = decode_wav(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(decoded.sample_rate, 16_000);
+        assert_eq!(decoded.samples.len(), 3);
+        assert_eq!(decoded.samples[0], 0.0);
+        assert!((decoded.samples[1] - 1.0).abs() < 1e-6);
+        assert!((decoded.samples[2] + 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn decode_wav_rejects_non_riff_data() {
+        let path = std::env::temp_dir().join("rlip_sync_test_non_riff.wav");
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(b"this is not a RIFF/WAVE file")
+            .unwrap();
+
+        let decoded = decode_wav(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(decoded.is_err());
+    }
+}
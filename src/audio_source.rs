@@ -0,0 +1,210 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// Upper bound on queued-but-undrained samples, independent of the source's
+// actual sample rate: generous enough (10s at a typical 48kHz device) to
+// absorb a slow `_process` frame, small enough to bound memory if nothing
+// ever drains the queue (e.g. no `LipSync` node is polling it).
+const MAX_QUEUED_SAMPLES: usize = 10 * 48_000;
+
+// Drops the oldest samples in `queue` so pushing `incoming` more doesn't
+// exceed `MAX_QUEUED_SAMPLES`.
+fn push_with_cap(queue: &mut VecDeque<f32>, incoming: &[f32]) {
+    queue.extend(incoming.iter().copied());
+    let overflow = queue.len().saturating_sub(MAX_QUEUED_SAMPLES);
+    if overflow > 0 {
+        queue.drain(..overflow);
+    }
+}
+
+/// Where `LipSync` gets its live input samples from. Lets the capture
+/// backend (a Godot "Record" bus tap, or a `cpal` input device outside
+/// Godot) stay separate from the analysis pipeline, which only ever talks
+/// to this trait.
+///
+/// `Send + Sync` because `LipSync` (which owns a `Box<dyn AudioSource>`)
+/// is a gdnative `NativeClass` backed by `RwLockData`, which requires the
+/// whole class to be `Send + Sync`.
+pub trait AudioSource: Send + Sync {
+    /// Sample rate of the underlying device/bus, in Hz.
+    fn samples_per_second(&self) -> f64;
+    /// Number of samples currently queued and not yet drained.
+    fn space_available(&self) -> usize;
+    /// Drains up to `out.len()` queued samples into `out`, returning how
+    /// many were written.
+    fn feed_samples(&mut self, out: &mut [f32]) -> usize;
+    /// Queues externally-supplied samples, e.g. from a Godot audio effect
+    /// callback. Sources that capture on their own (like `cpal`) ignore this.
+    fn push_samples(&mut self, _samples: &[f32]) {}
+    /// Notifies the source of a sample-rate change (the Godot bus tap is
+    /// re-synced to `AudioServer.get_mix_rate()` every frame). Sources whose
+    /// rate is fixed by the hardware, like `cpal`, ignore this.
+    fn set_samples_per_second(&mut self, _rate: f64) {}
+}
+
+/// Godot "Record" bus tap: samples pushed by `LipSync::on_data_received`
+/// are queued here until the main thread drains them in `_process`.
+pub struct GodotAudioSource {
+    mix_rate: f64,
+    queue: VecDeque<f32>,
+}
+
+impl GodotAudioSource {
+    pub fn new(mix_rate: f64) -> Self {
+        GodotAudioSource {
+            mix_rate,
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl AudioSource for GodotAudioSource {
+    fn samples_per_second(&self) -> f64 {
+        self.mix_rate
+    }
+
+    fn space_available(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn feed_samples(&mut self, out: &mut [f32]) -> usize {
+        let n = out.len().min(self.queue.len());
+        for slot in out.iter_mut().take(n) {
+            *slot = self.queue.pop_front().unwrap();
+        }
+        n
+    }
+
+    fn push_samples(&mut self, samples: &[f32]) {
+        push_with_cap(&mut self.queue, samples);
+    }
+
+    fn set_samples_per_second(&mut self, rate: f64) {
+        self.mix_rate = rate;
+    }
+}
+
+// How often the stream-owning thread wakes to check for shutdown, once the
+// stream itself is up and running.
+const STREAM_THREAD_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Default input device capture via `cpal`; needs no gdnative runtime.
+/// `LipSync` does not construct one: it always reads from a
+/// `GodotAudioSource`.
+///
+/// `cpal::Stream` is deliberately `!Send`/`!Sync` on every platform (see
+/// `cpal::platform::NotSendSyncAcrossAllPlatforms`), so it can't live in
+/// this struct directly — `LipSync` (and hence every `AudioSource` it
+/// holds) must stay `Send + Sync`. Instead the stream is built and kept
+/// alive on a dedicated thread that this struct only talks to via the
+/// shared ring buffer and a shutdown flag.
+pub struct CpalAudioSource {
+    sample_rate: f64,
+    buffer: Arc<Mutex<VecDeque<f32>>>,
+    shutdown: Arc<AtomicBool>,
+    stream_thread: Option<thread::JoinHandle<()>>,
+}
+
+impl CpalAudioSource {
+    pub fn new() -> Result<Self, String> {
+        use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+        let host = cpal::default_host();
+        let device = host
+            .default_input_device()
+            .ok_or_else(|| "no default input device".to_string())?;
+        let config: cpal::SupportedStreamConfig =
+            device.default_input_config().map_err(|e| e.to_string())?;
+
+        let sample_rate = config.sample_rate().0 as f64;
+        let channels = config.channels().max(1) as usize;
+        let sample_format = config.sample_format();
+        let buffer = Arc::new(Mutex::new(VecDeque::new()));
+        let stream_buffer = buffer.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let thread_shutdown = shutdown.clone();
+
+        let (started_tx, started_rx) = mpsc::channel::<Result<(), String>>();
+
+        let stream_thread = thread::spawn(move || {
+            let stream: Result<cpal::Stream, String> = match sample_format {
+                cpal::SampleFormat::F32 => device
+                    .build_input_stream(
+                        &config.into(),
+                        move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                            let mut buffer = stream_buffer.lock().unwrap();
+                            let mono: Vec<f32> =
+                                data.chunks(channels).map(|frame| frame[0]).collect();
+                            push_with_cap(&mut buffer, &mono);
+                        },
+                        |err| eprintln!("rlip-sync: cpal input stream error: {}", err),
+                        None,
+                    )
+                    .map_err(|e| e.to_string()),
+                other => Err(format!("unsupported cpal sample format: {:?}", other)),
+            };
+
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = started_tx.send(Err(e));
+                    return;
+                }
+            };
+
+            if let Err(e) = stream.play() {
+                let _ = started_tx.send(Err(e.to_string()));
+                return;
+            }
+            let _ = started_tx.send(Ok(()));
+
+            // The stream captures on cpal's own audio thread; this thread
+            // just has to keep `stream` alive until told to stop.
+            while !thread_shutdown.load(Ordering::Acquire) {
+                thread::sleep(STREAM_THREAD_POLL_INTERVAL);
+            }
+        });
+
+        started_rx
+            .recv()
+            .map_err(|_| "cpal stream thread exited before starting".to_string())??;
+
+        Ok(CpalAudioSource {
+            sample_rate,
+            buffer,
+            shutdown,
+            stream_thread: Some(stream_thread),
+        })
+    }
+}
+
+impl Drop for CpalAudioSource {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Release);
+        if let Some(stream_thread) = self.stream_thread.take() {
+            let _ = stream_thread.join();
+        }
+    }
+}
+
+impl AudioSource for CpalAudioSource {
+    fn samples_per_second(&self) -> f64 {
+        self.sample_rate
+    }
+
+    fn space_available(&self) -> usize {
+        self.buffer.lock().unwrap().len()
+    }
+
+    fn feed_samples(&mut self, out: &mut [f32]) -> usize {
+        let mut buffer = self.buffer.lock().unwrap();
+        let n = out.len().min(buffer.len());
+        for slot in out.iter_mut().take(n) {
+            *slot = buffer.pop_front().unwrap();
+        }
+        n
+    }
+}
@@ -0,0 +1,34 @@
+/// Snapshot of the most recently computed lip-sync result, emitted once per
+/// `_process` via the `lip_sync_updated` signal.
+#[derive(Clone, Debug)]
+pub struct LipSyncInfo {
+    pub index: i64,
+    pub phoneme: String,
+    pub volume: f64,
+    pub raw_volume: f64,
+    pub distance: f64,
+}
+
+impl LipSyncInfo {
+    pub fn new(index: i64, phoneme: String, volume: f64, raw_volume: f64, distance: f64) -> Self {
+        LipSyncInfo {
+            index,
+            phoneme,
+            volume,
+            raw_volume,
+            distance,
+        }
+    }
+}
+
+impl Default for LipSyncInfo {
+    fn default() -> Self {
+        LipSyncInfo {
+            index: -1,
+            phoneme: String::new(),
+            volume: 0.0,
+            raw_volume: 0.0,
+            distance: 0.0,
+        }
+    }
+}
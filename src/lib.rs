@@ -0,0 +1,15 @@
+mod audio_file;
+mod audio_source;
+mod common;
+mod lip_sync;
+mod lip_sync_job;
+mod loudness;
+mod profile;
+
+use gdnative::prelude::*;
+
+fn init(handle: InitHandle) {
+    handle.add_class::<lip_sync::LipSync>();
+}
+
+godot_init!(init);
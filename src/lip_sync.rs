@@ -1,12 +1,26 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
 use gdnative::{
     api::{AudioEffect, AudioServer},
     prelude::*,
 };
 
+use crate::audio_source::{AudioSource, GodotAudioSource};
 use crate::common::LipSyncInfo;
 use crate::lip_sync_job::*;
 use crate::profile::*;
 
+// What the worker thread hands back per finished job: the MFCC it computed
+// (for `mfcc_for_other`/calibration), the updated noise-gate state (floor
+// and smoothed gain per mel band), and the phoneme match.
+type JobOutput = (Vec<f64>, Vec<f64>, Vec<f64>, LipSyncJobResult);
+
+// How much recent audio `auto_calibrate_loudness` measures over.
+const LOUDNESS_WINDOW_SECONDS: f64 = 5.0;
+
 #[derive(NativeClass)]
 #[inherit(Reference)]
 #[user_data(user_data::RwLockData<LipSync>)]
@@ -29,9 +43,84 @@ pub struct LipSync {
     job_result: Vec<LipSyncJobResult>,
     requested_calibration_vowels: Vec<i64>,
 
+    // Where live input samples come from: always the Godot "Record" bus
+    // tap. There is no way to install another `AudioSource` (such as a
+    // `CpalAudioSource`), and `_init`/`_process` query Godot's
+    // `AudioServer` whichever source is used.
+    audio_source: Box<dyn AudioSource>,
+
+    // Rolling window of raw input samples (at the audio source's native
+    // rate) feeding `auto_calibrate_loudness`.
+    loudness_window: Vec<f64>,
+
+    // Pre-MFCC spectral noise gate state, persisted across jobs like `mfcc`.
+    noise_floor: Vec<f64>,
+    smoothed_gain: Vec<f64>,
+    // Seconds left in a `learn_noise_profile` capture window; 0 when idle.
+    learn_noise_remaining: f64,
+
+    // Background analysis worker: `schedule_job` hands a job across
+    // `job_tx`, the worker runs the MFCC/distance math off-thread, and
+    // `update_result` drains `result_rx` each frame. `Receiver` is `Send`
+    // but not `Sync`, and `LipSync` (a gdnative `NativeClass` backed by
+    // `RwLockData`) has to be both, hence the `Mutex` — only `_process`
+    // (single-threaded, behind `&mut self`) ever touches it.
+    job_tx: Option<Sender<LipSyncJob>>,
+    result_rx: Option<Mutex<Receiver<JobOutput>>>,
+    worker_busy: Arc<AtomicBool>,
+
+    // Latest raw (un-smoothed) analysis, refreshed whenever a job result
+    // comes in; `apply_envelope` runs every frame to smooth these into
+    // `result`.
+    target_index: i64,
+    target_phoneme: String,
+    target_volume: f64,
+    target_raw_volume: f64,
+    target_distance: f64,
+
+    // One-pole attack/release follower state for `result.volume`.
+    smoothed_volume: f64,
+    // Minimum-dwell-time gate for `result.phoneme`/`result.index`.
+    pending_phoneme_index: i64,
+    dwell_time: f64,
+
     result: LipSyncInfo,
 }
 
+// One-pole attack/release coefficient for smoothing `volume` towards
+// `target` over `delta` seconds: `attack_time`/`release_time` is how long
+// (in seconds) it takes to close most of the gap, whichever applies given
+// the direction of travel.
+fn volume_smoothing_coef(volume: f64, target: f64, delta: f64, attack_time: f64, release_time: f64) -> f64 {
+    let frames_per_sec = if delta > 0.0 { 1.0 / delta } else { 60.0 };
+    let time_const = if target > volume { attack_time } else { release_time };
+    (-1.0 / (time_const * frames_per_sec)).exp()
+}
+
+// Decides whether the result should switch to `target_index`: immediately
+// if it's already the current result, otherwise only once it's been the
+// pending target for at least `dwell_time_required` seconds. Returns the
+// updated `(pending_index, dwell_time)` alongside the switch decision.
+fn dwell_gate(
+    target_index: i64,
+    current_index: i64,
+    pending_index: i64,
+    dwell_time: f64,
+    delta: f64,
+    dwell_time_required: f64,
+) -> (i64, f64, bool) {
+    let (pending_index, dwell_time) = if target_index == current_index {
+        (pending_index, 0.0)
+    } else if target_index == pending_index {
+        (pending_index, dwell_time + delta)
+    } else {
+        (target_index, delta)
+    };
+
+    let switch = target_index == current_index || dwell_time >= dwell_time_required;
+    (pending_index, dwell_time, switch)
+}
+
 #[methods]
 impl LipSync {
     fn new(_owner: &Reference) -> Self {
@@ -51,6 +140,27 @@ impl LipSync {
             job_result: vec![],
             requested_calibration_vowels: vec![],
 
+            audio_source: Box::new(GodotAudioSource::new(44100.0)),
+            loudness_window: vec![],
+
+            noise_floor: vec![],
+            smoothed_gain: vec![],
+            learn_noise_remaining: 0.0,
+
+            job_tx: None,
+            result_rx: None,
+            worker_busy: Arc::new(AtomicBool::new(false)),
+
+            target_index: -1,
+            target_phoneme: String::new(),
+            target_volume: 0.0,
+            target_raw_volume: 0.0,
+            target_distance: 0.0,
+
+            smoothed_volume: 0.0,
+            pending_phoneme_index: -1,
+            dwell_time: 0.0,
+
             result: LipSyncInfo::default(),
         }
     }
@@ -71,21 +181,46 @@ impl LipSync {
     #[export]
     fn _init(&mut self, _owner: &Reference) {
         self.update_audio_source();
+        self.start_worker();
+    }
+
+    // Spawns the dedicated analysis thread and wires up the SPSC channels
+    // `schedule_job`/`update_result` talk over.
+    fn start_worker(&mut self) {
+        let (job_tx, job_rx) = std::sync::mpsc::channel::<LipSyncJob>();
+        let (result_tx, result_rx) = std::sync::mpsc::channel::<JobOutput>();
+        let busy = self.worker_busy.clone();
+
+        thread::spawn(move || {
+            for mut job in job_rx {
+                job.execute();
+                let result = job.result.get(0).cloned().unwrap_or_default();
+                // If the receiving end is gone there's nothing left to do;
+                // drop the result and let the thread exit on the next recv.
+                let _ = result_tx.send((job.mfcc, job.noise_floor, job.smoothed_gain, result));
+                busy.store(false, Ordering::Release);
+            }
+        });
+
+        self.job_tx = Some(job_tx);
+        self.result_rx = Some(Mutex::new(result_rx));
     }
 
     // Maps to Update() in the Unity impl
     #[export]
-    fn _process(&mut self, owner: &Reference) {
+    fn _process(&mut self, owner: &Reference, delta: f64) {
         //
 
-        self.update_result();
+        self.update_result(delta);
         self.invoke_callback(owner);
         self.update_calibration();
+        self.update_noise_learning(delta);
         self.update_phonemes();
         self.schedule_job();
 
         self.update_buffers();
         self.update_audio_source();
+        self.pull_audio_source();
     }
 
     fn awake() {
@@ -101,12 +236,12 @@ impl LipSync {
     }
 
     fn allocate_buffers(&mut self) {
-        self.raw_input_data = vec![];
-        self.input_data = vec![];
-        self.mfcc = vec![];
-        self.mfcc_for_other = vec![];
+        self.raw_input_data = vec![0.0; self.input_sample_count() as usize];
+        self.input_data = vec![0.0; self.profile.sample_count as usize];
+        self.mfcc = vec![0.0; MFCC_COEFFICIENT_COUNT];
+        self.mfcc_for_other = vec![0.0; MFCC_COEFFICIENT_COUNT];
         self.job_result = vec![];
-        self.phonemes = vec![];
+        self.phonemes = self.flatten_phonemes();
     }
 
     fn dispose_buffers(&mut self) {
@@ -120,30 +255,101 @@ impl LipSync {
 
     fn update_buffers(&mut self) {
         if self.input_sample_count() != self.raw_input_data.len() as i64
-            || self.profile.mfccs.len() * 12 != self.phonemes.len()
+            || self.profile.mfccs.len() * MFCC_COEFFICIENT_COUNT != self.phonemes.len()
         {
             self.dispose_buffers();
             self.allocate_buffers();
         }
     }
 
-    fn update_result(&mut self) {
-        // wait for thread to complete
-        // TODO stub
+    fn update_result(&mut self, delta: f64) {
+        // Non-blockingly drain whatever the worker has finished, keeping
+        // only the latest result so a slow frame can't build a backlog
+        // (equivalent to `pop_latest`). If nothing has finished yet, the
+        // previous target values (and hence `self.result`) are untouched.
+        let mut latest: Option<JobOutput> = None;
+        if let Some(result_rx) = &self.result_rx {
+            let result_rx = result_rx.lock().unwrap();
+            while let Ok(output) = result_rx.try_recv() {
+                latest = Some(output);
+            }
+        }
+
+        if let Some((mfcc, noise_floor, smoothed_gain, job_result)) = latest {
+            self.mfcc = mfcc;
+            // A job dispatched before `allocate_buffers` has run (e.g. the
+            // very first frame) carries an empty `mfcc`, which can race
+            // back in after `mfcc_for_other` has already been sized; resize
+            // to match rather than assume the lengths agree.
+            if self.mfcc_for_other.len() != self.mfcc.len() {
+                self.mfcc_for_other = vec![0.0; self.mfcc.len()];
+            }
+            self.mfcc_for_other.copy_from_slice(&self.mfcc);
+            self.job_result = vec![job_result.clone()];
+            self.noise_floor = noise_floor;
+            self.smoothed_gain = smoothed_gain;
+
+            self.target_index = job_result.index;
+            self.target_phoneme = if self.target_index < 0 {
+                String::new()
+            } else {
+                self.profile.get_phoneme(self.target_index as usize)
+            };
+            self.target_distance = job_result.distance;
+            self.target_raw_volume = job_result.volume;
+            self.target_volume = self.normalize_volume(job_result.volume);
+        }
 
-        self.mfcc_for_other.copy_from_slice(&self.mfcc);
+        self.apply_envelope(delta);
+    }
 
-        // TODO hopefully they're not just using lists as their main data structure
-        let index = self.job_result[0].index;
-        let phoneme = self.profile.get_phoneme(index as usize);
-        let distance = self.job_result[0].distance;
-        let mut vol = self.job_result[0].volume.log10();
+    // One-pole attack/release follower on `result.volume`, plus a
+    // minimum-dwell-time gate on `result.index`/`result.phoneme` so a
+    // single noisy frame can't flip the detected phoneme.
+    fn apply_envelope(&mut self, delta: f64) {
+        let coef = volume_smoothing_coef(
+            self.smoothed_volume,
+            self.target_volume,
+            delta,
+            self.profile.attack_time,
+            self.profile.release_time,
+        );
+        self.smoothed_volume = coef * self.smoothed_volume + (1.0 - coef) * self.target_volume;
+
+        let (pending_index, dwell_time, switch) = dwell_gate(
+            self.target_index,
+            self.result.index,
+            self.pending_phoneme_index,
+            self.dwell_time,
+            delta,
+            self.profile.phoneme_dwell_time,
+        );
+        self.pending_phoneme_index = pending_index;
+        self.dwell_time = dwell_time;
+
+        let (index, phoneme) = if switch {
+            (self.target_index, self.target_phoneme.clone())
+        } else {
+            (self.result.index, self.result.phoneme.clone())
+        };
+
+        self.result = LipSyncInfo::new(
+            index,
+            phoneme,
+            self.smoothed_volume,
+            self.target_raw_volume,
+            self.target_distance,
+        );
+    }
+
+    // Maps a raw (linear) volume onto `Profile.min_volume`/`max_volume` in
+    // the log10 domain, shared by the live (`update_result`) and offline
+    // (`bake_from_file`) paths.
+    fn normalize_volume(&self, raw_volume: f64) -> f64 {
         let min_vol = self.profile.min_volume;
         let max_vol = self.profile.max_volume.max(min_vol + 1e-4_f64);
-        vol = (vol - min_vol) / (max_vol - min_vol);
-        vol = f64::clamp(vol, 0.0, 1.0);
-
-        self.result = LipSyncInfo::new(index, phoneme, vol, self.job_result[0].volume, distance);
+        let vol = (raw_volume.log10() - min_vol) / (max_vol - min_vol);
+        f64::clamp(vol, 0.0, 1.0)
     }
 
     fn invoke_callback(&mut self, owner: &Reference) {
@@ -167,26 +373,40 @@ impl LipSync {
     }
 
     fn schedule_job(&mut self) {
-        // TODO incomplete, this is the hard part
-        let mut index: i64 = 0;
+        // Skip dispatch while the worker is still chewing on the previous
+        // job rather than cloning (and queuing up) a new one every frame.
+        if self.worker_busy.load(Ordering::Acquire) {
+            return;
+        }
+
+        let job_tx = match &self.job_tx {
+            Some(job_tx) => job_tx,
+            None => return,
+        };
 
         self.input_data.clone_from(&self.raw_input_data);
-        index = self.index;
 
-        // TODO cloning for now, we might actually need a reference
         let job = LipSyncJob {
             input: self.input_data.clone(),
-            start_index: index,
-            output_sample_rate: AudioServer::godot_singleton().get_mix_rate() as i64,
+            start_index: self.index,
+            output_sample_rate: self.audio_source.samples_per_second() as i64,
             target_sample_rate: self.profile.target_sample_rate,
             volume_thresh: (10.0 as f64).powf(self.profile.min_volume),
             mel_filter_bank_channels: self.profile.mel_filter_bank_channels,
             mfcc: self.mfcc.clone(),
             phonemes: self.phonemes.clone(),
             result: self.job_result.clone(),
+            noise_floor: self.noise_floor.clone(),
+            smoothed_gain: self.smoothed_gain.clone(),
+            noise_reduction: self.profile.noise_reduction,
+            learning_noise: self.learn_noise_remaining > 0.0,
         };
 
-        // TODO run on thread
+        self.worker_busy.store(true, Ordering::Release);
+        if job_tx.send(job).is_err() {
+            // Worker thread is gone; don't get stuck thinking it's busy.
+            self.worker_busy.store(false, Ordering::Release);
+        }
     }
 
     #[export]
@@ -208,28 +428,207 @@ impl LipSync {
         self.requested_calibration_vowels.clear();
     }
 
+    // Starts a capture window during which the noise gate's per-band floor
+    // snaps straight to the instantaneous energy instead of only tracking
+    // its minimum, so a few seconds of silence is enough to learn it.
+    // Feed this a quiet/background-only sample.
+    #[export]
+    fn learn_noise_profile(&mut self, _owner: &Reference, seconds: f64) {
+        self.learn_noise_remaining = seconds.max(0.0);
+    }
+
+    fn update_noise_learning(&mut self, delta: f64) {
+        if self.learn_noise_remaining > 0.0 {
+            self.learn_noise_remaining = (self.learn_noise_remaining - delta).max(0.0);
+        }
+    }
+
+    // Measures EBU R128 integrated loudness over the last
+    // `LOUDNESS_WINDOW_SECONDS` of captured audio and derives
+    // `Profile.min_volume`/`max_volume` from it, instead of requiring the
+    // user to tune those thresholds by hand.
+    #[export]
+    fn auto_calibrate_loudness(&mut self, _owner: &Reference) {
+        let sample_rate = self.audio_source.samples_per_second();
+        let (integrated_lufs, quietest_block_lufs) =
+            match crate::loudness::integrated_loudness(&self.loudness_window, sample_rate) {
+                Some(measured) => measured,
+                None => {
+                    godot_warn!(
+                        "rlip-sync: not enough audio captured yet to auto-calibrate loudness"
+                    );
+                    return;
+                }
+            };
+
+        // Convert LUFS (L = -0.691 + 20*log10(rms)) back into log10(rms), the
+        // domain `Profile.min_volume`/`max_volume` and `normalize_volume`
+        // already work in.
+        let lufs_to_log10_rms = |lufs: f64| (lufs + 0.691) / 20.0;
+
+        self.profile.min_volume = lufs_to_log10_rms(quietest_block_lufs);
+        self.profile.max_volume = lufs_to_log10_rms(integrated_lufs);
+    }
+
+    // Decodes `path` (WAV/OGG/FLAC) and slides the same analysis window
+    // `schedule_job` uses across it at a fixed `hop_size` (in samples, at
+    // the profile's `target_sample_rate`), so dialogue can be pre-baked
+    // into a sequence of `LipSyncInfo` frames instead of analyzed live.
+    #[export]
+    fn bake_from_file(
+        &mut self,
+        _owner: &Reference,
+        path: GodotString,
+        hop_size: i64,
+    ) -> Dictionary {
+        let path = path.to_string();
+        let decoded = match crate::audio_file::decode_file(&path) {
+            Ok(decoded) => decoded,
+            Err(err) => {
+                godot_error!("rlip-sync: failed to bake '{}': {}", path, err);
+                return Dictionary::new().into_shared();
+            }
+        };
+
+        let samples = crate::audio_file::resample(
+            &decoded.samples,
+            decoded.sample_rate,
+            self.profile.target_sample_rate,
+        );
+
+        let window_len = self.profile.sample_count.max(1) as usize;
+        let hop = hop_size.max(1) as usize;
+        let volume_thresh = (10.0 as f64).powf(self.profile.min_volume);
+        let phonemes = self.flatten_phonemes();
+
+        // Noise-gate state threaded across hops so the floor/gain estimate
+        // builds up over the file, the same way it persists across frames
+        // in the live path.
+        let mut noise_floor = vec![];
+        let mut smoothed_gain = vec![];
+
+        let frames = Array::new();
+        let mut start = 0usize;
+        while start < samples.len() {
+            let end = (start + window_len).min(samples.len());
+            let mut window = samples[start..end].to_vec();
+            window.resize(window_len, 0.0);
+
+            let mut job = LipSyncJob {
+                input: window,
+                start_index: 0,
+                output_sample_rate: self.profile.target_sample_rate,
+                target_sample_rate: self.profile.target_sample_rate,
+                volume_thresh,
+                mel_filter_bank_channels: self.profile.mel_filter_bank_channels,
+                mfcc: vec![],
+                phonemes: phonemes.clone(),
+                result: vec![],
+                noise_floor,
+                smoothed_gain,
+                noise_reduction: self.profile.noise_reduction,
+                learning_noise: false,
+            };
+            job.execute();
+            let job_result = job.result[0].clone();
+            noise_floor = job.noise_floor;
+            smoothed_gain = job.smoothed_gain;
+
+            let phoneme = if job_result.index < 0 {
+                String::new()
+            } else {
+                self.profile.get_phoneme(job_result.index as usize)
+            };
+
+            let frame = Dictionary::new();
+            frame.insert("index", job_result.index);
+            frame.insert("phoneme", phoneme);
+            frame.insert("volume", self.normalize_volume(job_result.volume));
+            frame.insert("raw_volume", job_result.volume);
+            frame.insert("distance", job_result.distance);
+            frame.insert(
+                "timestamp",
+                start as f64 / self.profile.target_sample_rate as f64,
+            );
+            frames.push(&Variant::from_dictionary(&frame.into_shared()));
+
+            start += hop;
+        }
+
+        let output = Dictionary::new();
+        output.insert("frames", frames.into_shared());
+        output.into_shared()
+    }
+
+    // Flattened `[phoneme][coefficient]` reference table, as consumed by
+    // `LipSyncJob.phonemes` (mirrors what `update_phonemes` maintains on
+    // `self.phonemes` for the live path).
+    fn flatten_phonemes(&self) -> Vec<f64> {
+        let mut flat =
+            Vec::with_capacity(self.profile.mfccs.len() * crate::profile::MFCC_COEFFICIENT_COUNT);
+        for data in self.profile.mfccs.iter() {
+            flat.extend_from_slice(&data.mfcc_native_array);
+        }
+        flat
+    }
+
     fn update_audio_source(&mut self) {
         let audio_server = AudioServer::godot_singleton();
         let record_effect_index = audio_server.get_bus_index("Record");
         self.effect = audio_server.get_bus_effect(record_effect_index, 0);
+
+        self.audio_source
+            .set_samples_per_second(audio_server.get_mix_rate());
     }
 
-    // TODO connect to some audio thing
-    // https://github.com/godot-rust/godot-rust/blob/0.9.3/examples/signals/src/lib.rs#L73
-    fn on_data_received(&mut self, _owner: &Reference, input: &mut TypedArray<f32>, channels: i64) {
-        if self.raw_input_data.len() == 0 {
+    // Drains whatever `self.audio_source` has queued into the ring buffer,
+    // through the trait rather than reading the Godot singleton directly.
+    fn pull_audio_source(&mut self) {
+        if self.raw_input_data.is_empty() {
             return;
         }
 
-        let n = self.raw_input_data.len() as i64;
-        self.index = self.index % n;
+        let available = self.audio_source.space_available();
+        if available == 0 {
+            return;
+        }
+
+        let mut drained = vec![0.0_f32; available];
+        let n = self.audio_source.feed_samples(&mut drained);
+        drained.truncate(n);
+
+        let len = self.raw_input_data.len() as i64;
+        self.index = self.index % len;
+        for &sample in &drained {
+            self.index = (self.index + 1) % len;
+            self.raw_input_data[self.index as usize] = sample as f64;
+        }
+
+        self.loudness_window
+            .extend(drained.iter().map(|&s| s as f64));
+        let cap =
+            (LOUDNESS_WINDOW_SECONDS * self.audio_source.samples_per_second()).round() as usize;
+        if self.loudness_window.len() > cap {
+            let overflow = self.loudness_window.len() - cap;
+            self.loudness_window.drain(0..overflow);
+        }
+    }
+
+    // TODO connect to some audio thing
+    // https://github.com/godot-rust/godot-rust/blob/0.9.3/examples/signals/src/lib.rs#L73
+    //
+    // Queues captured samples (first channel only) on `audio_source` for
+    // `pull_audio_source` to drain. It is not exported, and nothing in the
+    // crate calls it.
+    fn on_data_received(&mut self, _owner: &Reference, input: &mut TypedArray<f32>, channels: i64) {
+        let channels = channels.max(1) as i32;
+        let mut samples = Vec::with_capacity(input.len() as usize / channels as usize);
         let mut i = 0;
         while i < input.len() {
-            self.index = (self.index + 1) % n;
-            self.raw_input_data[self.index as usize] = input.get(i as i32).into();
-
-            i += channels as i32;
+            samples.push(input.get(i));
+            i += channels;
         }
+        self.audio_source.push_samples(&samples);
 
         if (self.output_sound_gain - 1.0).abs() > f64::EPSILON {
             let n = input.len() as i32;
@@ -264,8 +663,66 @@ impl LipSync {
 
     // Changed from property in the Unity impl to function
     fn input_sample_count(&self) -> i64 {
-        let r =
-            AudioServer::godot_singleton().get_mix_rate() / self.profile.target_sample_rate as f64;
+        let r = self.audio_source.samples_per_second() / self.profile.target_sample_rate as f64;
         (self.profile.sample_count as f64 * r).ceil() as i64
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_smoothing_coef_is_in_unit_range() {
+        let coef = volume_smoothing_coef(0.0, 1.0, 1.0 / 60.0, 0.1, 0.2);
+        assert!((0.0..1.0).contains(&coef));
+    }
+
+    #[test]
+    fn volume_smoothing_coef_uses_attack_time_when_rising() {
+        let rising = volume_smoothing_coef(0.0, 1.0, 1.0 / 60.0, 0.05, 0.5);
+        let falling = volume_smoothing_coef(1.0, 0.0, 1.0 / 60.0, 0.05, 0.5);
+        // A much shorter attack time than release time should smooth less
+        // (lower coef) while rising than while falling.
+        assert!(rising < falling);
+    }
+
+    #[test]
+    fn volume_smoothing_coef_falls_back_to_60fps_for_nonpositive_delta() {
+        let a = volume_smoothing_coef(0.0, 1.0, 0.0, 0.1, 0.1);
+        let b = volume_smoothing_coef(0.0, 1.0, 1.0 / 60.0, 0.1, 0.1);
+        assert!((a - b).abs() < 1e-12);
+    }
+
+    #[test]
+    fn dwell_gate_switches_immediately_when_already_current() {
+        let (pending, dwell, switch) = dwell_gate(3, 3, 3, 0.5, 1.0 / 60.0, 0.2);
+        assert_eq!(pending, 3);
+        assert_eq!(dwell, 0.0);
+        assert!(switch);
+    }
+
+    #[test]
+    fn dwell_gate_holds_until_dwell_time_elapses() {
+        let (pending, dwell, switch) = dwell_gate(5, 3, 5, 0.1, 0.05, 0.2);
+        assert_eq!(pending, 5);
+        assert!((dwell - 0.15).abs() < 1e-9);
+        assert!(!switch);
+    }
+
+    #[test]
+    fn dwell_gate_switches_once_dwell_time_is_reached() {
+        let (pending, dwell, switch) = dwell_gate(5, 3, 5, 0.18, 0.05, 0.2);
+        assert_eq!(pending, 5);
+        assert!((dwell - 0.23).abs() < 1e-9);
+        assert!(switch);
+    }
+
+    #[test]
+    fn dwell_gate_restarts_on_a_new_target() {
+        let (pending, dwell, switch) = dwell_gate(7, 3, 5, 0.18, 0.05, 0.2);
+        assert_eq!(pending, 7);
+        assert!((dwell - 0.05).abs() < 1e-9);
+        assert!(!switch);
+    }
+}
@@ -0,0 +1,415 @@
+use crate::profile::MFCC_COEFFICIENT_COUNT;
+use std::f64::consts::PI;
+
+// Per-mel-band noise floor tracking time constants: quick to drop onto a
+// new, quieter energy, slow (a few seconds) to rise back up once noise
+// clears, and the gain itself smoothed across frames to avoid chattering.
+const NOISE_FLOOR_RISE_SECONDS: f64 = 3.0;
+const NOISE_GAIN_SMOOTHING_SECONDS: f64 = 0.1;
+
+/// Result of analyzing a single audio window: nearest phoneme index/distance
+/// and the measured volume. Maps to the `LipSyncJob.Result` output in the
+/// Unity impl.
+#[derive(Clone, Debug, Default)]
+pub struct LipSyncJobResult {
+    pub index: i64,
+    pub distance: f64,
+    pub volume: f64,
+}
+
+/// One unit of MFCC/phoneme-distance work. Everything the job needs is
+/// cloned in up front so it can run off the main thread without touching
+/// `LipSync` state.
+#[derive(Clone)]
+pub struct LipSyncJob {
+    pub input: Vec<f64>,
+    pub start_index: i64,
+    pub output_sample_rate: i64,
+    pub target_sample_rate: i64,
+    pub volume_thresh: f64,
+    pub mel_filter_bank_channels: i64,
+
+    // Working buffer for this frame's MFCC, read back afterwards via
+    // `LipSync::mfcc()`.
+    pub mfcc: Vec<f64>,
+    // Flattened `[phoneme][coefficient]` reference table copied out of
+    // `Profile::mfccs`.
+    pub phonemes: Vec<f64>,
+    pub result: Vec<LipSyncJobResult>,
+
+    // Per-mel-band noise gate state, threaded in/out like `mfcc` so the
+    // floor estimate and smoothed gain persist across jobs.
+    pub noise_floor: Vec<f64>,
+    pub smoothed_gain: Vec<f64>,
+    // 0 = gate disabled (pass through), 1 = full computed attenuation.
+    pub noise_reduction: f64,
+    // While learning (see `LipSync::learn_noise_profile`), the floor snaps
+    // straight to the instantaneous band energy instead of only tracking
+    // its minimum, so a few seconds of silence is enough to capture it.
+    pub learning_noise: bool,
+}
+
+impl LipSyncJob {
+    // Maps to LipSyncJob.Execute() in the Unity impl.
+    pub fn execute(&mut self) {
+        let window = self.resample_window();
+
+        let volume = rms(&window);
+
+        if self.result.is_empty() {
+            self.result.push(LipSyncJobResult::default());
+        }
+
+        // Computed (and the noise floor updated) even on silent frames so
+        // `learn_noise_profile` can capture the floor from a quiet sample.
+        let windowed = hann_window(&window);
+        let spectrum = power_spectrum(&windowed);
+        let mel = mel_filter_bank(
+            &spectrum,
+            self.target_sample_rate,
+            self.mel_filter_bank_channels as usize,
+        );
+        let gated_mel = self.apply_noise_gate(&mel);
+
+        if volume < self.volume_thresh {
+            self.result[0] = LipSyncJobResult {
+                index: -1,
+                distance: f64::MAX,
+                volume,
+            };
+            return;
+        }
+
+        let log_mel: Vec<f64> = gated_mel.iter().map(|v| (v.max(1e-10)).ln()).collect();
+        self.mfcc = dct(&log_mel, MFCC_COEFFICIENT_COUNT);
+
+        let (index, distance) = self.nearest_phoneme();
+
+        self.result[0] = LipSyncJobResult {
+            index,
+            distance,
+            volume,
+        };
+    }
+
+    // RNNoise-style spectral gate: tracks a per-band noise floor and
+    // attenuates each mel band by a gain derived from its instantaneous-to-
+    // floor SNR, smoothed across frames so the gate doesn't chatter.
+    fn apply_noise_gate(&mut self, mel: &[f64]) -> Vec<f64> {
+        if self.noise_floor.len() != mel.len() {
+            self.noise_floor = mel.to_vec();
+            self.smoothed_gain = vec![1.0; mel.len()];
+        }
+
+        // Each job analyzes one window this long; use it as the dt for the
+        // floor-rise and gain-smoothing one-pole coefficients.
+        let window_seconds = self.input.len() as f64 / self.output_sample_rate.max(1) as f64;
+
+        noise_gate(
+            mel,
+            &mut self.noise_floor,
+            &mut self.smoothed_gain,
+            self.noise_reduction,
+            self.learning_noise,
+            window_seconds,
+        )
+    }
+
+    // Reads the whole ring buffer `input`, starting at `start_index`,
+    // down-sampling it from `output_sample_rate` to `target_sample_rate` via
+    // nearest-neighbour decimation.
+    fn resample_window(&self) -> Vec<f64> {
+        let n = self.input.len();
+        if n == 0 {
+            return vec![];
+        }
+
+        let ratio = self.output_sample_rate as f64 / self.target_sample_rate as f64;
+        let out_len = (n as f64 / ratio).round() as usize;
+        let mut window = Vec::with_capacity(out_len);
+        for i in 0..out_len {
+            let src = (self.start_index as f64 + i as f64 * ratio) as i64;
+            let src = src.rem_euclid(n as i64) as usize;
+            window.push(self.input[src]);
+        }
+        window
+    }
+
+    fn nearest_phoneme(&self) -> (i64, f64) {
+        let coeffs = MFCC_COEFFICIENT_COUNT;
+        let phoneme_count = self.phonemes.len() / coeffs.max(1);
+
+        let mut best_index: i64 = -1;
+        let mut best_distance = f64::MAX;
+
+        for p in 0..phoneme_count {
+            let reference = &self.phonemes[p * coeffs..(p + 1) * coeffs];
+            let distance: f64 = self
+                .mfcc
+                .iter()
+                .zip(reference.iter())
+                .map(|(a, b)| (a - b).powi(2))
+                .sum();
+
+            if distance < best_distance {
+                best_distance = distance;
+                best_index = p as i64;
+            }
+        }
+
+        (best_index, best_distance.sqrt())
+    }
+}
+
+// Per-mel-band spectral gate: tracks a per-band noise floor in `noise_floor`
+// (quick to drop onto a new, quieter energy, slow to rise back up once
+// noise clears) and attenuates each band by a gain derived from its
+// instantaneous-to-floor SNR, itself smoothed in `smoothed_gain` across
+// calls so the gate doesn't chatter. `noise_reduction` scales the applied
+// attenuation (0 disables the gate, 1 applies it fully); `learning_noise`
+// snaps the floor straight to the instantaneous energy instead of only
+// tracking its minimum, so a few seconds of silence is enough to capture
+// it; `dt_seconds` is the time this call's window covers, used for the
+// one-pole coefficients. Whenever `noise_floor`'s length doesn't match
+// `mel`'s, both are reinitialized: the floor to `mel` itself and every
+// smoothed gain to 1.0.
+fn noise_gate(
+    mel: &[f64],
+    noise_floor: &mut Vec<f64>,
+    smoothed_gain: &mut Vec<f64>,
+    noise_reduction: f64,
+    learning_noise: bool,
+    dt_seconds: f64,
+) -> Vec<f64> {
+    if noise_floor.len() != mel.len() {
+        *noise_floor = mel.to_vec();
+        *smoothed_gain = vec![1.0; mel.len()];
+    }
+
+    let rise_coef = (-dt_seconds / NOISE_FLOOR_RISE_SECONDS).exp();
+    let gain_coef = (-dt_seconds / NOISE_GAIN_SMOOTHING_SECONDS).exp();
+
+    let mut gated = vec![0.0; mel.len()];
+    for i in 0..mel.len() {
+        let energy = mel[i];
+
+        if learning_noise || energy < noise_floor[i] {
+            noise_floor[i] = energy;
+        } else {
+            noise_floor[i] = rise_coef * noise_floor[i] + (1.0 - rise_coef) * energy;
+        }
+
+        let floor = noise_floor[i].max(1e-10);
+        let snr = energy / floor;
+        let gain = snr / (snr + 1.0);
+        smoothed_gain[i] = gain_coef * smoothed_gain[i] + (1.0 - gain_coef) * gain;
+
+        let applied_gain = (1.0 - noise_reduction * (1.0 - smoothed_gain[i])).clamp(0.0, 1.0);
+        gated[i] = energy * applied_gain;
+    }
+
+    gated
+}
+
+fn rms(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum_sq: f64 = samples.iter().map(|s| s * s).sum();
+    (sum_sq / samples.len() as f64).sqrt()
+}
+
+fn hann_window(samples: &[f64]) -> Vec<f64> {
+    let n = samples.len();
+    if n <= 1 {
+        return samples.to_vec();
+    }
+    samples
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let w = 0.5 - 0.5 * (2.0 * PI * i as f64 / (n - 1) as f64).cos();
+            s * w
+        })
+        .collect()
+}
+
+// Direct-form DFT power (squared-magnitude) spectrum (bin 0..n/2). The analysis windows used
+// here are small enough that an O(n^2) DFT is cheap compared to the rest of
+// the job and keeps the implementation dependency-free.
+fn power_spectrum(samples: &[f64]) -> Vec<f64> {
+    let n = samples.len();
+    let half = n / 2 + 1;
+    let mut spectrum = vec![0.0; half];
+
+    for (k, bin) in spectrum.iter_mut().enumerate() {
+        let mut re = 0.0;
+        let mut im = 0.0;
+        for (i, s) in samples.iter().enumerate() {
+            let angle = -2.0 * PI * k as f64 * i as f64 / n as f64;
+            re += s * angle.cos();
+            im += s * angle.sin();
+        }
+        *bin = re * re + im * im;
+    }
+
+    spectrum
+}
+
+fn mel_filter_bank(power: &[f64], sample_rate: i64, channels: usize) -> Vec<f64> {
+    let n_fft = (power.len() - 1) * 2;
+    let nyquist = sample_rate as f64 / 2.0;
+
+    let hz_to_mel = |hz: f64| 2595.0 * (1.0 + hz / 700.0).log10();
+    let mel_to_hz = |mel: f64| 700.0 * (10f64.powf(mel / 2595.0) - 1.0);
+
+    let mel_min = hz_to_mel(0.0);
+    let mel_max = hz_to_mel(nyquist);
+
+    let mut mel_points = Vec::with_capacity(channels + 2);
+    for i in 0..channels + 2 {
+        let mel = mel_min + (mel_max - mel_min) * i as f64 / (channels + 1) as f64;
+        mel_points.push(mel_to_hz(mel));
+    }
+
+    let bin_of = |hz: f64| ((n_fft as f64 + 1.0) * hz / sample_rate as f64) as usize;
+
+    let mut output = vec![0.0; channels];
+    for m in 0..channels {
+        let left = bin_of(mel_points[m]);
+        let center = bin_of(mel_points[m + 1]);
+        let right = bin_of(mel_points[m + 2]);
+
+        let mut energy = 0.0;
+        for k in left..center.min(power.len()) {
+            if center > left {
+                energy += power[k] * (k - left) as f64 / (center - left) as f64;
+            }
+        }
+        for k in center..right.min(power.len()) {
+            if right > center {
+                energy += power[k] * (right - k) as f64 / (right - center) as f64;
+            }
+        }
+        output[m] = energy;
+    }
+
+    output
+}
+
+// Type-II DCT, keeping only the first `count` coefficients (the standard
+// cepstral truncation used to turn a mel spectrum into MFCCs).
+fn dct(input: &[f64], count: usize) -> Vec<f64> {
+    let n = input.len();
+    let mut output = Vec::with_capacity(count);
+
+    for k in 0..count {
+        let mut sum = 0.0;
+        for (i, v) in input.iter().enumerate() {
+            sum += v * (PI / n as f64 * (i as f64 + 0.5) * k as f64).cos();
+        }
+        output.push(sum);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rms_of_silence_is_zero() {
+        assert_eq!(rms(&[0.0; 8]), 0.0);
+    }
+
+    #[test]
+    fn rms_of_constant_signal_equals_its_magnitude() {
+        assert!((rms(&[0.5; 8]) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rms_of_empty_is_zero() {
+        assert_eq!(rms(&[]), 0.0);
+    }
+
+    #[test]
+    fn hann_window_tapers_edges_to_zero() {
+        let windowed = hann_window(&[1.0; 5]);
+        assert!((windowed[0]).abs() < 1e-9);
+        assert!((windowed[4]).abs() < 1e-9);
+        assert!(windowed[2] > windowed[0]);
+    }
+
+    #[test]
+    fn hann_window_passes_through_short_signals() {
+        assert_eq!(hann_window(&[1.0]), vec![1.0]);
+        assert_eq!(hann_window(&[]), Vec::<f64>::new());
+    }
+
+    #[test]
+    fn power_spectrum_of_silence_is_zero() {
+        let spectrum = power_spectrum(&[0.0; 16]);
+        assert!(spectrum.iter().all(|&v| v.abs() < 1e-9));
+    }
+
+    #[test]
+    fn power_spectrum_has_nyquist_plus_one_bins() {
+        let spectrum = power_spectrum(&[1.0; 8]);
+        assert_eq!(spectrum.len(), 5);
+    }
+
+    #[test]
+    fn mel_filter_bank_of_silence_is_zero() {
+        let spectrum = vec![0.0; 65];
+        let mel = mel_filter_bank(&spectrum, 16_000, 12);
+        assert_eq!(mel.len(), 12);
+        assert!(mel.iter().all(|&v| v.abs() < 1e-9));
+    }
+
+    #[test]
+    fn dct_of_constant_input_concentrates_energy_in_dc_term() {
+        let input = vec![1.0; 8];
+        let coeffs = dct(&input, 4);
+        assert_eq!(coeffs.len(), 4);
+        assert!(coeffs[0].abs() > coeffs[1].abs());
+    }
+
+    #[test]
+    fn noise_gate_resizes_state_on_first_call() {
+        let mut floor = vec![];
+        let mut gain = vec![];
+        noise_gate(&[1.0, 2.0, 3.0], &mut floor, &mut gain, 1.0, false, 0.01);
+        assert_eq!(floor.len(), 3);
+        assert_eq!(gain.len(), 3);
+    }
+
+    #[test]
+    fn noise_gate_disabled_passes_energy_through_unchanged() {
+        let mel = vec![1.0, 2.0, 3.0];
+        let mut floor = vec![0.1; 3];
+        let mut gain = vec![1.0; 3];
+        let gated = noise_gate(&mel, &mut floor, &mut gain, 0.0, false, 0.01);
+        for (g, m) in gated.iter().zip(mel.iter()) {
+            assert!((g - m).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn noise_gate_attenuates_energy_near_the_floor() {
+        // Energy right at the tracked floor has SNR 1, so full-strength
+        // gating should knock it down noticeably below its raw value.
+        let mut floor = vec![1.0];
+        let mut gain = vec![1.0];
+        let gated = noise_gate(&[1.0], &mut floor, &mut gain, 1.0, false, 0.01);
+        assert!(gated[0] < 1.0);
+    }
+
+    #[test]
+    fn noise_gate_learning_snaps_floor_to_instantaneous_energy() {
+        let mut floor = vec![10.0];
+        let mut gain = vec![1.0];
+        noise_gate(&[0.5], &mut floor, &mut gain, 1.0, true, 0.01);
+        assert_eq!(floor[0], 0.5);
+    }
+}
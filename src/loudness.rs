@@ -0,0 +1,192 @@
+// EBU R128 integrated-loudness measurement, used by
+// `LipSync::auto_calibrate_loudness` to derive `Profile.min_volume`/
+// `max_volume` instead of requiring the user to tune fixed thresholds.
+
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = 10.0;
+const BLOCK_SECONDS: f64 = 0.4;
+const BLOCK_OVERLAP: f64 = 0.75;
+
+// A biquad stage of the K-weighting filter, direct form II transposed.
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    // RBJ audio-eq-cookbook high shelf.
+    fn high_shelf(sample_rate: f64, freq: f64, gain_db: f64, q: f64) -> Self {
+        let a = 10f64.powf(gain_db / 40.0);
+        let w0 = 2.0 * std::f64::consts::PI * freq / sample_rate;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos_w0 = w0.cos();
+        let two_sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        let b0 = a * ((a + 1.0) + (a - 1.0) * cos_w0 + two_sqrt_a_alpha);
+        let b1 = -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_w0);
+        let b2 = a * ((a + 1.0) + (a - 1.0) * cos_w0 - two_sqrt_a_alpha);
+        let a0 = (a + 1.0) - (a - 1.0) * cos_w0 + two_sqrt_a_alpha;
+        let a1 = 2.0 * ((a - 1.0) - (a + 1.0) * cos_w0);
+        let a2 = (a + 1.0) - (a - 1.0) * cos_w0 - two_sqrt_a_alpha;
+
+        Biquad {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    // RBJ audio-eq-cookbook high pass.
+    fn high_pass(sample_rate: f64, freq: f64, q: f64) -> Self {
+        let w0 = 2.0 * std::f64::consts::PI * freq / sample_rate;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos_w0 = w0.cos();
+
+        let b0 = (1.0 + cos_w0) / 2.0;
+        let b1 = -(1.0 + cos_w0);
+        let b2 = (1.0 + cos_w0) / 2.0;
+        let a0 = 1.0 + alpha;
+        let a1 = -2.0 * cos_w0;
+        let a2 = 1.0 - alpha;
+
+        Biquad {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+// ITU-R BS.1770 K-weighting: a high-shelf "head" boost (models the head's
+// acoustic effect) followed by a high-pass (removes rumble).
+struct KWeightingFilter {
+    head: Biquad,
+    high_pass: Biquad,
+}
+
+impl KWeightingFilter {
+    fn new(sample_rate: f64) -> Self {
+        KWeightingFilter {
+            head: Biquad::high_shelf(sample_rate, 1681.97, 4.0, 0.7071),
+            high_pass: Biquad::high_pass(sample_rate, 38.13, 0.5003),
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        self.high_pass.process(self.head.process(x))
+    }
+}
+
+fn loudness_of(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.max(1e-12).log10()
+}
+
+/// Measures EBU R128 integrated loudness (LUFS) over `samples` at
+/// `sample_rate`, gated per the standard: blocks below the absolute gate
+/// (-70 LUFS) are discarded, then blocks more than 10 LU below the mean of
+/// the survivors are discarded too, and the integrated loudness is the mean
+/// of what's left. Also returns the quietest surviving block's loudness.
+/// Returns `None` if there isn't enough audio for a single 400ms block, or
+/// every block is gated out.
+pub fn integrated_loudness(samples: &[f64], sample_rate: f64) -> Option<(f64, f64)> {
+    let block_len = (BLOCK_SECONDS * sample_rate).round() as usize;
+    if block_len == 0 || samples.len() < block_len {
+        return None;
+    }
+    let hop = ((block_len as f64) * (1.0 - BLOCK_OVERLAP))
+        .round()
+        .max(1.0) as usize;
+
+    let mut filter = KWeightingFilter::new(sample_rate);
+    let filtered: Vec<f64> = samples.iter().map(|&s| filter.process(s)).collect();
+
+    let mut block_energies = Vec::new();
+    let mut start = 0;
+    while start + block_len <= filtered.len() {
+        let mean_square: f64 = filtered[start..start + block_len]
+            .iter()
+            .map(|v| v * v)
+            .sum::<f64>()
+            / block_len as f64;
+        block_energies.push(mean_square);
+        start += hop;
+    }
+
+    let absolute_survivors: Vec<f64> = block_energies
+        .into_iter()
+        .filter(|&ms| loudness_of(ms) >= ABSOLUTE_GATE_LUFS)
+        .collect();
+    if absolute_survivors.is_empty() {
+        return None;
+    }
+
+    let absolute_mean = absolute_survivors.iter().sum::<f64>() / absolute_survivors.len() as f64;
+    let relative_threshold = loudness_of(absolute_mean) - RELATIVE_GATE_LU;
+
+    let relative_survivors: Vec<f64> = absolute_survivors
+        .into_iter()
+        .filter(|&ms| loudness_of(ms) >= relative_threshold)
+        .collect();
+    if relative_survivors.is_empty() {
+        return None;
+    }
+
+    let integrated_mean = relative_survivors.iter().sum::<f64>() / relative_survivors.len() as f64;
+    let quietest_block = relative_survivors
+        .iter()
+        .copied()
+        .map(loudness_of)
+        .fold(f64::INFINITY, f64::min);
+
+    Some((loudness_of(integrated_mean), quietest_block))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn silence_is_gated_out() {
+        let samples = vec![0.0; 48_000];
+        assert_eq!(integrated_loudness(&samples, 48_000.0), None);
+    }
+
+    #[test]
+    fn too_short_for_one_block_returns_none() {
+        let samples = vec![0.5; 100];
+        assert_eq!(integrated_loudness(&samples, 48_000.0), None);
+    }
+
+    #[test]
+    fn full_scale_tone_is_louder_than_quiet_tone() {
+        let tone = |amplitude: f64| -> Vec<f64> {
+            (0..48_000)
+                .map(|i| amplitude * (2.0 * std::f64::consts::PI * 440.0 * i as f64 / 48_000.0).sin())
+                .collect()
+        };
+
+        let loud = integrated_loudness(&tone(0.9), 48_000.0).unwrap();
+        let quiet = integrated_loudness(&tone(0.1), 48_000.0).unwrap();
+        assert!(loud.0 > quiet.0);
+    }
+}
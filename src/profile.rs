@@ -0,0 +1,102 @@
+/// Reference MFCC data for a single calibrated phoneme (e.g. "A", "I", "U").
+#[derive(Clone)]
+pub struct MfccData {
+    pub name: String,
+    pub mfcc_native_array: Vec<f64>,
+    // Number of samples averaged into `mfcc_native_array` so far, used to
+    // weight incremental calibration updates.
+    sample_count: i64,
+}
+
+impl MfccData {
+    pub fn new(name: &str) -> Self {
+        MfccData {
+            name: name.to_string(),
+            mfcc_native_array: vec![0.0; MFCC_COEFFICIENT_COUNT],
+            sample_count: 0,
+        }
+    }
+}
+
+pub const MFCC_COEFFICIENT_COUNT: usize = 12;
+
+/// User-tunable analysis settings, maps to the `Profile` ScriptableObject in
+/// the Unity implementation.
+pub struct Profile {
+    pub mfccs: Vec<MfccData>,
+
+    pub target_sample_rate: i64,
+    pub sample_count: i64,
+    pub mel_filter_bank_channels: i64,
+
+    pub min_volume: f64,
+    pub max_volume: f64,
+
+    // One-pole attack/release follower time constants (seconds) applied to
+    // `LipSyncInfo.volume`.
+    pub attack_time: f64,
+    pub release_time: f64,
+    // Minimum time (seconds) a candidate phoneme must persist before
+    // `LipSyncInfo.index`/`phoneme` switches to it.
+    pub phoneme_dwell_time: f64,
+
+    // Strength of the pre-MFCC spectral noise gate: 0 disables it, 1
+    // applies the full computed per-band attenuation.
+    pub noise_reduction: f64,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Profile {
+            mfccs: vec![
+                MfccData::new("A"),
+                MfccData::new("I"),
+                MfccData::new("U"),
+                MfccData::new("E"),
+                MfccData::new("O"),
+            ],
+
+            target_sample_rate: 16000,
+            sample_count: 1024,
+            mel_filter_bank_channels: 24,
+
+            min_volume: -3.0,
+            max_volume: -1.0,
+
+            attack_time: 0.05,
+            release_time: 0.12,
+            phoneme_dwell_time: 0.05,
+
+            noise_reduction: 0.5,
+        }
+    }
+
+    pub fn get_phoneme(&self, index: usize) -> String {
+        self.mfccs
+            .get(index)
+            .map(|data| data.name.clone())
+            .unwrap_or_default()
+    }
+
+    // Maps to UpdateMfcc() in the Unity impl: blends a freshly measured MFCC
+    // into the stored reference for `index`, either averaging it in
+    // (`add == true`) or overwriting the reference outright.
+    pub fn update_mfcc(&mut self, index: usize, mfcc: Vec<f64>, add: bool) {
+        let data = match self.mfccs.get_mut(index) {
+            Some(data) => data,
+            None => return,
+        };
+
+        if !add || data.sample_count == 0 {
+            data.mfcc_native_array = mfcc;
+            data.sample_count = 1;
+            return;
+        }
+
+        let n = data.sample_count as f64;
+        for (stored, sample) in data.mfcc_native_array.iter_mut().zip(mfcc.iter()) {
+            *stored = (*stored * n + *sample) / (n + 1.0);
+        }
+        data.sample_count += 1;
+    }
+}